@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+
+use crate::broker::topic::{self, MatchCache};
+use crate::broker::{Router, SubRelations, SubRelationsMap, SubscriptionId};
+use crate::{ClientId, Id, NodeId, QoS, Result, SharedGroup, TopicFilter, TopicName};
+
+type HashMap<K, V> = std::collections::HashMap<K, V, ahash::RandomState>;
+
+#[derive(Clone)]
+struct Relation {
+    client_id: ClientId,
+    qos: QoS,
+    shared_group: Option<SharedGroup>,
+    subscription_id: Option<SubscriptionId>,
+}
+
+///Single-node, in-memory `Router`. Relations are kept in a flat map from topic filter to the
+///relations registered against it; `matches` is served from `MatchCache` when possible and
+///otherwise falls back to a linear scan via `topic::matches`. `add`/`remove` invalidate the
+///whole cache (`MatchCache::invalidate_all`) rather than just the topics touched by the changed
+///filter: a scoped invalidation can't see topics that haven't been cached yet, so a relation
+///added between another call's scan and its cache insert could go unnoticed and stick around
+///stale until an unrelated change happened to evict it. Bumping the generation instead means any
+///entry inserted from a stale scan is detected and discarded on its next lookup, no matter when
+///the write landed relative to the scan.
+pub struct DefaultRouter {
+    node_id: NodeId,
+    relations: RwLock<HashMap<TopicFilter, Vec<Relation>>>,
+    cache: MatchCache,
+    topics_max: AtomicUsize,
+    relations_max: AtomicUsize,
+}
+
+impl DefaultRouter {
+    pub fn new(node_id: NodeId, cache_capacity: usize) -> Self {
+        Self {
+            node_id,
+            relations: RwLock::new(HashMap::default()),
+            cache: MatchCache::new(cache_capacity),
+            topics_max: AtomicUsize::new(0),
+            relations_max: AtomicUsize::new(0),
+        }
+    }
+
+    fn bump_maxes(&self) {
+        let relations = self.relations.read();
+        let topics = relations.len();
+        let total: usize = relations.values().map(|rs| rs.len()).sum();
+        drop(relations);
+        if topics > self.topics_max.load(Ordering::SeqCst) {
+            self.topics_max.store(topics, Ordering::SeqCst);
+        }
+        if total > self.relations_max.load(Ordering::SeqCst) {
+            self.relations_max.store(total, Ordering::SeqCst);
+        }
+    }
+}
+
+#[async_trait]
+impl Router for DefaultRouter {
+    async fn add(
+        &self,
+        topic_filter: &str,
+        id: Id,
+        qos: QoS,
+        shared_group: Option<SharedGroup>,
+        subscription_id: Option<SubscriptionId>,
+    ) -> Result<()> {
+        let filter = TopicFilter::from(topic_filter.to_string());
+        {
+            let mut relations = self.relations.write();
+            let relations_for_filter = relations.entry(filter).or_insert_with(Vec::new);
+            relations_for_filter.retain(|r| r.client_id != id.client_id);
+            relations_for_filter.push(Relation { client_id: id.client_id, qos, shared_group, subscription_id });
+        }
+        self.bump_maxes();
+        self.cache.invalidate_all();
+        Ok(())
+    }
+
+    async fn remove(&self, topic_filter: &str, id: Id) -> Result<bool> {
+        let removed = {
+            let mut relations = self.relations.write();
+            let filter = TopicFilter::from(topic_filter.to_string());
+            match relations.get_mut(&filter) {
+                Some(relations_for_filter) => {
+                    let before = relations_for_filter.len();
+                    relations_for_filter.retain(|r| r.client_id != id.client_id);
+                    let removed = relations_for_filter.len() != before;
+                    if relations_for_filter.is_empty() {
+                        relations.remove(&filter);
+                    }
+                    removed
+                }
+                None => false,
+            }
+        };
+        if removed {
+            self.cache.invalidate_all();
+        }
+        Ok(removed)
+    }
+
+    async fn matches(&self, topic: &TopicName) -> Result<SubRelationsMap> {
+        if let Some(cached) = self.cache.get(topic) {
+            return Ok(cached);
+        }
+
+        // Captured before the scan, so a concurrent `add`/`remove` whose invalidation lands at any
+        // point after this read leaves the entry stamped with an older generation than current.
+        let generation = self.cache.generation();
+        let mut matched: SubRelations = Vec::new();
+        for (filter, relations_for_filter) in self.relations.read().iter() {
+            if !topic::matches(topic.as_ref(), filter.as_ref()) {
+                continue;
+            }
+            for r in relations_for_filter {
+                matched.push((
+                    filter.clone(),
+                    r.client_id.clone(),
+                    r.qos,
+                    // `Relation` tracks no connectivity state, so the real online check is left
+                    // to `SharedSubscription::choice`, which already falls back to
+                    // `Router::is_online` when given `None`.
+                    r.shared_group.clone().map(|g| (g, None)),
+                    r.subscription_id,
+                ));
+            }
+        }
+
+        let mut map = SubRelationsMap::default();
+        map.insert(self.node_id, matched);
+        self.cache.insert(topic.clone(), generation, map.clone());
+        Ok(map)
+    }
+
+    #[inline]
+    fn topics_max(&self) -> usize {
+        self.topics_max.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn topics(&self) -> usize {
+        self.relations.read().len()
+    }
+
+    #[inline]
+    fn relations_max(&self) -> usize {
+        self.relations_max.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    fn relations(&self) -> usize {
+        self.relations.read().values().map(|rs| rs.len()).sum()
+    }
+
+    async fn list_topics(&self, top: usize) -> Vec<String> {
+        self.relations.read().keys().take(top).map(|f| f.to_string()).collect()
+    }
+
+    async fn list_relations(&self, top: usize) -> Vec<serde_json::Value> {
+        self.relations
+            .read()
+            .iter()
+            .take(top)
+            .map(|(filter, rs)| serde_json::json!({ "topic_filter": filter.to_string(), "count": rs.len() }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(client_id: &str) -> Id {
+        Id::from(1, ClientId::from(client_id))
+    }
+
+    #[tokio::test]
+    async fn matches_sees_a_relation_added_after_the_topic_was_first_cached() {
+        let router = DefaultRouter::new(1, 8);
+        let topic = TopicName::from("a/b".to_string());
+
+        // First lookup caches an empty result for this topic.
+        let before = router.matches(&topic).await.unwrap();
+        assert!(before.get(&1).unwrap().is_empty());
+
+        // A relation added afterwards must invalidate that cached empty result, not leave it
+        // stuck forever - this is the generation-bump the cache relies on to self-heal.
+        router.add("a/b", id("c1"), QoS::AtMostOnce, None, None).await.unwrap();
+        let after = router.matches(&topic).await.unwrap();
+        assert_eq!(after.get(&1).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn remove_invalidates_a_cached_match_too() {
+        let router = DefaultRouter::new(1, 8);
+        let topic = TopicName::from("a/b".to_string());
+
+        router.add("a/b", id("c1"), QoS::AtMostOnce, None, None).await.unwrap();
+        let before = router.matches(&topic).await.unwrap();
+        assert_eq!(before.get(&1).unwrap().len(), 1);
+
+        router.remove("a/b", id("c1")).await.unwrap();
+        let after = router.matches(&topic).await.unwrap();
+        assert!(after.get(&1).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn shared_relations_leave_the_online_check_to_choice() {
+        let router = DefaultRouter::new(1, 8);
+        router.add("a/b", id("c1"), QoS::AtMostOnce, Some(SharedGroup::from("g1".to_string())), None).await.unwrap();
+
+        let map = router.matches(&TopicName::from("a/b".to_string())).await.unwrap();
+        let (_, _, _, shared, _) = &map.get(&1).unwrap()[0];
+        let (_, is_online) = shared.as_ref().unwrap();
+        assert_eq!(*is_online, None);
+    }
+}
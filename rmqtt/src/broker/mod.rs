@@ -38,7 +38,19 @@ pub trait Entry: Sync + Send {
     fn client(&self) -> Option<ClientInfo>;
     fn exist(&self) -> bool;
     fn tx(&self) -> Option<Tx>;
+    ///Subscribe; `subscribe.subscription_id` is echoed back on matching PUBLISH
     async fn subscribe(&self, subscribe: &Subscribe) -> Result<SubscribeReturn>;
+
+    ///Provided helper: implementations of `subscribe` call this before `router.add` and, on
+    ///`Some`, reject the subscription with that reason instead of adding the relation
+    #[inline]
+    async fn check_subscribe_quota(
+        &self,
+        router: &dyn Router,
+        max_active_subscriptions: usize,
+    ) -> Option<SubscribeQuotaReason> {
+        router.quota_exceeded(max_active_subscriptions)
+    }
     async fn unsubscribe(&self, unsubscribe: &Unsubscribe) -> Result<bool>;
     async fn publish(&self, from: From, p: Publish) -> Result<(), (From, Publish, Reason)>;
 }
@@ -54,7 +66,7 @@ pub trait Shared: Sync + Send {
     ///
     fn exist(&self, client_id: &str) -> bool;
 
-    ///Route and dispense publish message
+    ///Route and dispense publish message, carrying Subscription Identifiers on each match
     async fn forwards(&self, from: From, publish: Publish) -> Result<(), Vec<(To, From, Publish, Reason)>>;
 
     ///Route and dispense publish message and return shared subscription relations
@@ -64,7 +76,9 @@ pub trait Shared: Sync + Send {
         publish: Publish,
     ) -> Result<SubRelationsMap, Vec<(To, From, Publish, Reason)>>;
 
-    ///dispense publish message
+    ///Dispense publish message to the given relations, carrying each one's Subscription Identifier.
+    ///A client can appear more than once in `relations` (overlapping filters); group with
+    ///`subscription_ids_by_client` first so every match is echoed, not just the last one.
     async fn forwards_to(
         &self,
         from: From,
@@ -72,6 +86,18 @@ pub trait Shared: Sync + Send {
         relations: SubRelations,
     ) -> Result<(), Vec<(To, From, Publish, Reason)>>;
 
+    ///Provided helper: implementations of `forwards_to` call this per target session before
+    ///pushing onto its outbound queue, so a single slow consumer can't buffer without bound
+    #[inline]
+    fn admit_to_queue(
+        &self,
+        queue: &queue::QueueStats,
+        payload_len: usize,
+        limit: &queue::QueueLimit,
+    ) -> queue::Admission {
+        queue.try_admit(payload_len, limit)
+    }
+
     ///Returns the number of current node connections
     async fn clients(&self) -> usize;
 
@@ -117,29 +143,66 @@ pub trait Shared: Sync + Send {
     }
 }
 
-pub type SharedSubRelations = HashMap<TopicFilterString, Vec<(SharedGroup, NodeId, ClientId, QoS, IsOnline)>>;
+///MQTT v5 Subscription Identifier, a varint in the range 1..=268_435_455
+pub type SubscriptionId = u32;
+
+pub type SharedSubRelations =
+    HashMap<TopicFilterString, Vec<(SharedGroup, NodeId, ClientId, QoS, IsOnline, Option<SubscriptionId>)>>;
 //key is TopicFilter
 pub type OtherSubRelations = HashMap<NodeId, Vec<TopicFilter>>; //In other nodes
 
-pub type SubRelations = Vec<(TopicFilter, ClientId, QoS, Option<(SharedGroup, IsOnline)>)>;
+///`Option<IsOnline>` mirrors `SharedSubscription::choice`'s `ncs` parameter: `None` means the
+///relation's online state wasn't checked at match time and `choice` must do the real check.
+pub type SubRelations =
+    Vec<(TopicFilter, ClientId, QoS, Option<(SharedGroup, Option<IsOnline>)>, Option<SubscriptionId>)>;
 pub type SubRelationsMap = HashMap<NodeId, SubRelations>;
 pub type ClearSubscriptions = bool;
 
+///Groups `relations` by client, collecting each client's Subscription Identifiers into one `Vec`.
+///A client can match the same publish through more than one overlapping filter, each carrying
+///its own identifier, and every one of them must be echoed back as a separate Subscription
+///Identifier property on the outgoing PUBLISH - not just the last match. Clients with no
+///identifier on any matching relation still get an entry, with an empty `Vec`, so callers don't
+///need to special-case "no property" separately from "one property".
+pub fn subscription_ids_by_client(relations: &SubRelations) -> HashMap<ClientId, Vec<SubscriptionId>> {
+    let mut by_client: HashMap<ClientId, Vec<SubscriptionId>> = HashMap::default();
+    for (_, client_id, _, _, subscription_id) in relations {
+        let ids = by_client.entry(client_id.clone()).or_insert_with(Vec::new);
+        if let Some(id) = subscription_id {
+            ids.push(*id);
+        }
+    }
+    by_client
+}
+
+///Why a subscribe attempt was rejected by `Router::quota_exceeded`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscribeQuotaReason {
+    ///`max_active_subscriptions` reached; v5 reason code 0x97, v3 `SubscribeReturnCode::Failure`
+    QuotaExceeded,
+}
+
+impl SubscribeQuotaReason {
+    ///MQTT v5 SUBACK reason code for this rejection
+    pub const V5_REASON_CODE: u8 = 0x97;
+}
+
 #[async_trait]
 pub trait Router: Sync + Send {
-    ///
+    ///Add a subscription relation, with an optional Subscription Identifier to echo back
     async fn add(
         &self,
         topic_filter: &str,
         id: Id,
         qos: QoS,
         shared_group: Option<SharedGroup>,
+        subscription_id: Option<SubscriptionId>,
     ) -> Result<()>;
 
     ///
     async fn remove(&self, topic_filter: &str, id: Id) -> Result<bool>;
 
-    ///
+    ///Returns every relation matching `topic`, one entry per relation, with its Subscription Identifier
     async fn matches(&self, topic: &TopicName) -> Result<SubRelationsMap>;
 
     ///Check online or offline
@@ -168,6 +231,16 @@ pub trait Router: Sync + Send {
     ///Returns the number of Subscription relationship
     fn relations(&self) -> usize;
 
+    ///Checked by `Entry::check_subscribe_quota` before `add`; `0` means unlimited
+    #[inline]
+    fn quota_exceeded(&self, max_active_subscriptions: usize) -> Option<SubscribeQuotaReason> {
+        if max_active_subscriptions > 0 && self.relations() >= max_active_subscriptions {
+            Some(SubscribeQuotaReason::QuotaExceeded)
+        } else {
+            None
+        }
+    }
+
     ///get topic tree
     async fn list_topics(&self, top: usize) -> Vec<String>;
 
@@ -227,16 +300,19 @@ pub trait RetainStorage: Sync + Send {
         listen_cfg.retain_available
     }
 
-    ///topic - concrete topic
+    ///topic - concrete topic. If `retain` carries a Message Expiry Interval, the stored entry
+    ///records an absolute expiry timestamp derived from it rather than the raw interval.
     async fn set(&self, topic: &TopicName, retain: Retain) -> Result<()>;
 
-    ///topic_filter - Topic filter
+    ///topic_filter - Topic filter. Entries whose expiry has passed are neither returned nor
+    ///counted as if they no longer exist; surviving entries have their Message Expiry Interval
+    ///rewritten to the time remaining as of this call, as the spec requires.
     async fn get(&self, topic_filter: &TopicFilter) -> Result<Vec<(TopicName, Retain)>>;
 
-    ///
+    ///Number of currently live (non-expired) retained messages.
     fn count(&self) -> isize;
 
-    ///
+    ///High-water mark of `count()`.
     fn max(&self) -> isize;
 }
 
@@ -250,5 +326,29 @@ pub trait Limiter: Sync + Send {
     async fn acquire(&self, handshakings: isize) -> Result<()>;
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn relation(
+        client_id: &str,
+        subscription_id: Option<SubscriptionId>,
+    ) -> (TopicFilter, ClientId, QoS, Option<(SharedGroup, Option<IsOnline>)>, Option<SubscriptionId>) {
+        (TopicFilter::from("a/b".to_string()), ClientId::from(client_id), QoS::AtMostOnce, None, subscription_id)
+    }
 
+    #[test]
+    fn groups_multiple_identifiers_for_the_same_client() {
+        let relations = vec![relation("c1", Some(1)), relation("c1", Some(2))];
+        let by_client = subscription_ids_by_client(&relations);
+        assert_eq!(by_client.get(&ClientId::from("c1")), Some(&vec![1, 2]));
+    }
+
+    #[test]
+    fn clients_with_no_identifier_still_get_an_empty_entry() {
+        let relations = vec![relation("c1", None)];
+        let by_client = subscription_ids_by_client(&relations);
+        assert_eq!(by_client.get(&ClientId::from("c1")), Some(&vec![]));
+    }
+}
 
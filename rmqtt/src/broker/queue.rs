@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::settings::listener::Listener;
+
+///What to do once a session's outbound queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    ///Drop the oldest buffered message to make room for the new one.
+    DropOldest,
+    ///Reject the new message, leaving the queue untouched.
+    RejectNew,
+}
+
+impl Default for OverflowPolicy {
+    #[inline]
+    fn default() -> Self {
+        OverflowPolicy::DropOldest
+    }
+}
+
+///Per-session outbound queue bounds, derived from listener settings. `0` means unbounded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueLimit {
+    ///Maximum number of buffered publishes.
+    pub max_messages: usize,
+    ///Maximum cumulative payload bytes buffered.
+    pub max_bytes: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl QueueLimit {
+    #[inline]
+    pub fn from_listener(listen_cfg: &Listener) -> Self {
+        Self {
+            max_messages: listen_cfg.queue_capacity,
+            max_bytes: listen_cfg.queue_capacity_bytes,
+            overflow: if listen_cfg.queue_reject_new {
+                OverflowPolicy::RejectNew
+            } else {
+                OverflowPolicy::DropOldest
+            },
+        }
+    }
+}
+
+///The result of attempting to admit a message into a bounded queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    ///There was room; the message was admitted.
+    Accepted,
+    ///Per `OverflowPolicy::DropOldest`, the caller must pop buffered messages from the front,
+    ///oldest first, until it has popped at least `messages` of them and freed at least `bytes`
+    ///bytes - `QueueStats` only tracks aggregate counters, not individual message sizes, so it
+    ///can't know how many messages that takes; the caller does, since it owns the real queue.
+    AcceptedEvictOldest { messages: usize, bytes: usize },
+    ///The message was dropped: either `OverflowPolicy::RejectNew` is in effect, or it alone
+    ///exceeds `max_bytes`, so no amount of eviction could ever make it fit.
+    Rejected,
+}
+
+///Atomic item- and byte-counters for one session's outbound queue; see `Shared::admit_to_queue`.
+#[derive(Debug, Default)]
+pub struct QueueStats {
+    messages: AtomicUsize,
+    bytes: AtomicUsize,
+}
+
+impl QueueStats {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.messages.load(Ordering::SeqCst)
+    }
+
+    #[inline]
+    pub fn byte_size(&self) -> usize {
+        self.bytes.load(Ordering::SeqCst)
+    }
+
+    ///Decides whether `payload_len` bytes may be admitted, updating the counters to reflect
+    ///the decision. On `AcceptedEvictOldest { messages, bytes }` the caller must pop at least
+    ///that many of the oldest buffered messages, and free at least that many bytes, calling
+    ///`on_pop` for each; there is never a promised eviction when the queue is empty.
+    pub fn try_admit(&self, payload_len: usize, limit: &QueueLimit) -> Admission {
+        if limit.max_bytes > 0 && payload_len > limit.max_bytes {
+            // Even evicting everything else buffered can't make this one fit.
+            return Admission::Rejected;
+        }
+        let len = self.len();
+        let bytes = self.byte_size();
+        let over_messages = limit.max_messages > 0 && len >= limit.max_messages;
+        let projected_bytes = bytes + payload_len;
+        let over_bytes = limit.max_bytes > 0 && projected_bytes > limit.max_bytes;
+        if !over_messages && !over_bytes {
+            self.messages.fetch_add(1, Ordering::SeqCst);
+            self.bytes.fetch_add(payload_len, Ordering::SeqCst);
+            return Admission::Accepted;
+        }
+        if len == 0 {
+            // Nothing buffered to evict.
+            return Admission::Rejected;
+        }
+        match limit.overflow {
+            OverflowPolicy::RejectNew => Admission::Rejected,
+            OverflowPolicy::DropOldest => {
+                self.messages.fetch_add(1, Ordering::SeqCst);
+                self.bytes.fetch_add(payload_len, Ordering::SeqCst);
+                let messages = if over_messages { 1 } else { 0 };
+                let bytes_to_free = if over_bytes { projected_bytes - limit.max_bytes } else { 0 };
+                Admission::AcceptedEvictOldest { messages, bytes: bytes_to_free }
+            }
+        }
+    }
+
+    ///Record that a previously admitted message of `payload_len` bytes has left the queue.
+    pub fn on_pop(&self, payload_len: usize) {
+        self.messages.fetch_sub(1, Ordering::SeqCst);
+        self.bytes.fetch_sub(payload_len, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admits_until_message_cap() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 2, max_bytes: 0, overflow: OverflowPolicy::RejectNew };
+        assert_eq!(stats.try_admit(10, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(10, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(10, &limit), Admission::Rejected);
+        assert_eq!(stats.len(), 2);
+    }
+
+    #[test]
+    fn admits_until_byte_cap() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 0, max_bytes: 15, overflow: OverflowPolicy::RejectNew };
+        assert_eq!(stats.try_admit(10, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(10, &limit), Admission::Rejected);
+        assert_eq!(stats.byte_size(), 10);
+    }
+
+    #[test]
+    fn drop_oldest_evicts_when_queue_non_empty() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 1, max_bytes: 0, overflow: OverflowPolicy::DropOldest };
+        assert_eq!(stats.try_admit(10, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(10, &limit), Admission::AcceptedEvictOldest { messages: 1, bytes: 0 });
+    }
+
+    #[test]
+    fn oversized_singleton_is_rejected_not_evicted() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 0, max_bytes: 5, overflow: OverflowPolicy::DropOldest };
+        // A message alone bigger than `max_bytes` can never fit, evictions or not, so it must
+        // be rejected rather than reported as `AcceptedEvictOldest`.
+        assert_eq!(stats.try_admit(10, &limit), Admission::Rejected);
+        assert_eq!(stats.len(), 0);
+        assert_eq!(stats.byte_size(), 0);
+    }
+
+    #[test]
+    fn oversized_arrival_is_rejected_even_with_something_small_buffered() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 0, max_bytes: 15, overflow: OverflowPolicy::DropOldest };
+        assert_eq!(stats.try_admit(1, &limit), Admission::Accepted);
+        // The 100-byte arrival alone exceeds `max_bytes`, so even evicting the buffered 1-byte
+        // message can't make it fit; it must be rejected outright.
+        assert_eq!(stats.try_admit(100, &limit), Admission::Rejected);
+        assert_eq!(stats.byte_size(), 1);
+    }
+
+    #[test]
+    fn drop_oldest_reports_every_byte_needed_to_fit_the_arrival() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit { max_messages: 0, max_bytes: 15, overflow: OverflowPolicy::DropOldest };
+        assert_eq!(stats.try_admit(5, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(5, &limit), Admission::Accepted);
+        assert_eq!(stats.try_admit(5, &limit), Admission::Accepted);
+        // byte_size() == 15 == max_bytes; a 15-byte arrival needs every existing byte freed, not
+        // just the single oldest message's worth.
+        assert_eq!(stats.try_admit(15, &limit), Admission::AcceptedEvictOldest { messages: 0, bytes: 15 });
+    }
+
+    #[test]
+    fn on_pop_reverses_accounting() {
+        let stats = QueueStats::new();
+        let limit = QueueLimit::default();
+        stats.try_admit(10, &limit);
+        stats.on_pop(10);
+        assert_eq!(stats.len(), 0);
+        assert_eq!(stats.byte_size(), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+
+use crate::broker::topic;
+use crate::broker::types::Retain;
+use crate::broker::RetainStorage;
+use crate::{Result, TopicFilter, TopicName};
+
+type HashMap<K, V> = std::collections::HashMap<K, V, ahash::RandomState>;
+
+#[inline]
+fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+///A stored retained message plus the absolute, epoch-second timestamp it expires at.
+///`expires_at == None` means it never expires.
+struct Entry {
+    retain: Retain,
+    expires_at: Option<i64>,
+}
+
+impl Entry {
+    fn new(retain: Retain, now: i64) -> Self {
+        let expires_at = retain.message_expiry_interval.map(|secs| now + secs as i64);
+        Self { retain, expires_at }
+    }
+
+    #[inline]
+    fn is_expired(&self, now: i64) -> bool {
+        matches!(self.expires_at, Some(at) if at <= now)
+    }
+
+    ///Message Expiry Interval remaining as of `now`, to be re-stamped onto the `Retain`
+    ///delivered to a subscriber - the spec requires the interval to reflect time already
+    ///elapsed since `set`, not the original value.
+    fn remaining(&self, now: i64) -> Option<u32> {
+        self.expires_at.map(|at| (at - now).max(0) as u32)
+    }
+}
+
+///Default `RetainStorage`: an in-memory map from concrete topic to its most recently retained
+///message, with MQTT v5 Message Expiry Interval support. Pair with `spawn_sweeper` so expired
+///entries are purged between publishes rather than only at lookup time.
+pub struct DefaultRetainStorage {
+    entries: RwLock<HashMap<TopicName, Entry>>,
+    max: AtomicIsize,
+}
+
+impl DefaultRetainStorage {
+    pub fn new() -> Self {
+        Self { entries: RwLock::new(HashMap::default()), max: AtomicIsize::new(0) }
+    }
+
+    ///Spawns a task that removes expired entries every `sweep_interval`, so retained messages
+    ///with a short Message Expiry Interval don't linger in memory until the next `get`.
+    pub fn spawn_sweeper(self: &Arc<Self>, sweep_interval: Duration) {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(sweep_interval);
+            loop {
+                tick.tick().await;
+                this.sweep();
+            }
+        });
+    }
+
+    fn sweep(&self) {
+        let now = now_secs();
+        self.entries.write().retain(|_, entry| !entry.is_expired(now));
+    }
+}
+
+impl Default for DefaultRetainStorage {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl RetainStorage for DefaultRetainStorage {
+    async fn set(&self, topic: &TopicName, retain: Retain) -> Result<()> {
+        let now = now_secs();
+        let mut entries = self.entries.write();
+        entries.insert(topic.clone(), Entry::new(retain, now));
+        // Count only live entries, not yet-unswept expired ones, so `max` (like `count`)
+        // never reports a high-water mark inflated by entries nobody can still retrieve.
+        let live = entries.values().filter(|entry| !entry.is_expired(now)).count() as isize;
+        if live > self.max.load(Ordering::SeqCst) {
+            self.max.store(live, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
+    async fn get(&self, topic_filter: &TopicFilter) -> Result<Vec<(TopicName, Retain)>> {
+        let now = now_secs();
+        let mut entries = self.entries.write();
+        let mut matched = Vec::new();
+        let mut expired = Vec::new();
+        for (topic, entry) in entries.iter() {
+            if entry.is_expired(now) {
+                expired.push(topic.clone());
+                continue;
+            }
+            if !topic::matches(topic.as_ref(), topic_filter.as_ref()) {
+                continue;
+            }
+            let mut retain = entry.retain.clone();
+            retain.message_expiry_interval = entry.remaining(now);
+            matched.push((topic.clone(), retain));
+        }
+        for topic in expired {
+            entries.remove(&topic);
+        }
+        Ok(matched)
+    }
+
+    #[inline]
+    fn count(&self) -> isize {
+        let now = now_secs();
+        self.entries.read().values().filter(|entry| !entry.is_expired(now)).count() as isize
+    }
+
+    #[inline]
+    fn max(&self) -> isize {
+        self.max.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    fn retain(message_expiry_interval: Option<u32>) -> Retain {
+        Retain { qos: crate::QoS::AtMostOnce, payload: Bytes::new(), publish_time: 0, message_expiry_interval }
+    }
+
+    #[test]
+    fn never_expires_without_an_interval() {
+        let entry = Entry::new(retain(None), 1_000);
+        assert!(!entry.is_expired(1_000_000));
+        assert_eq!(entry.remaining(1_000_000), None);
+    }
+
+    #[test]
+    fn expires_after_its_interval_elapses() {
+        let entry = Entry::new(retain(Some(10)), 1_000);
+        assert!(!entry.is_expired(1_009));
+        assert!(entry.is_expired(1_010));
+    }
+
+    #[test]
+    fn remaining_counts_down_as_time_passes() {
+        let entry = Entry::new(retain(Some(10)), 1_000);
+        assert_eq!(entry.remaining(1_000), Some(10));
+        assert_eq!(entry.remaining(1_004), Some(6));
+        assert_eq!(entry.remaining(1_010), Some(0));
+    }
+
+    #[test]
+    fn dollar_topics_are_excluded_from_leading_wildcard_filters() {
+        assert!(!topic::matches("$SYS/brokers/count", "#"));
+        assert!(topic::matches("$SYS/brokers/count", "$SYS/#"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::convert::From as _f;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::broker::types::{From, Publish, Retain};
+use crate::{NodeId, QoS, Runtime, TopicName};
+
+///A point-in-time snapshot of the scalar counters `Router` and `Shared` already expose
+///programmatically, shaped for publishing under `$SYS/brokers/<node>/...` so an ordinary MQTT
+///client can monitor the broker without a side-channel API.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Meters {
+    pub node_id: NodeId,
+    pub topics: usize,
+    pub topics_max: usize,
+    pub relations: usize,
+    pub relations_max: usize,
+    pub subscriptions: usize,
+    pub subscriptions_shared: usize,
+    pub clients: usize,
+    pub sessions: usize,
+}
+
+impl Meters {
+    ///Snapshot this node's own `Router`/`Shared` counters.
+    pub async fn this_node(node_id: NodeId) -> Self {
+        let router = Runtime::instance().extends.router().await;
+        let shared = Runtime::instance().extends.shared().await;
+        Self {
+            node_id,
+            topics: router.topics(),
+            topics_max: router.topics_max(),
+            relations: router.relations(),
+            relations_max: router.relations_max(),
+            subscriptions: shared.subscriptions(),
+            subscriptions_shared: shared.subscriptions_shared(),
+            clients: shared.clients().await,
+            sessions: shared.sessions().await,
+        }
+    }
+
+    ///Cluster-wide aggregate: this node's own counters, with `clients`/`sessions` replaced by
+    ///`Shared::all_clients`/`all_sessions` totals across every node reachable through
+    ///`Shared::get_grpc_clients`. `subscriptions`/`subscriptions_shared` have no cross-node
+    ///equivalent yet, so they're left as this node's own counts - callers must not publish
+    ///those two fields from this snapshot under a cluster-wide topic.
+    pub async fn all_nodes(node_id: NodeId) -> Self {
+        let mut total = Self::this_node(node_id).await;
+        let shared = Runtime::instance().extends.shared().await;
+        total.clients = shared.all_clients().await;
+        total.sessions = shared.all_sessions().await;
+        total
+    }
+}
+
+///Configuration for the periodic `$SYS` meter publisher.
+#[derive(Debug, Clone)]
+pub struct MetersConfig {
+    ///How often to snapshot and republish the meters.
+    pub interval: Duration,
+    ///Topic prefix the meters are published under, e.g. `"$SYS/brokers"`.
+    pub topic_prefix: String,
+}
+
+impl Default for MetersConfig {
+    #[inline]
+    fn default() -> Self {
+        Self { interval: Duration::from_secs(60), topic_prefix: "$SYS/brokers".into() }
+    }
+}
+
+///Spawns a task that snapshots `Meters` every `cfg.interval` and publishes the current-node
+///and all-nodes views as retained messages under `cfg.topic_prefix`.
+pub fn spawn(node_id: NodeId, cfg: MetersConfig) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(cfg.interval);
+        loop {
+            tick.tick().await;
+            let this_node = Meters::this_node(node_id).await;
+            let all_nodes = Meters::all_nodes(node_id).await;
+            publish(&cfg.topic_prefix, node_id, &this_node, &all_nodes).await;
+        }
+    });
+}
+
+///Builds the `$SYS` topic for a per-node meter, e.g. `$SYS/brokers/1/clients/count`.
+fn node_meter_topic(prefix: &str, node_id: NodeId, suffix: &str) -> String {
+    format!("{}/{}/{}", prefix, node_id, suffix)
+}
+
+///Builds the `$SYS` topic for a cluster-wide meter, e.g. `$SYS/brokers/cluster/clients/count`.
+fn cluster_meter_topic(prefix: &str, suffix: &str) -> String {
+    format!("{}/cluster/{}", prefix, suffix)
+}
+
+async fn publish(prefix: &str, node_id: NodeId, this_node: &Meters, all_nodes: &Meters) {
+    let retain_storage = Runtime::instance().extends.retain().await;
+    let shared = Runtime::instance().extends.shared().await;
+    let per_node = [
+        ("subscriptions/count", this_node.subscriptions),
+        ("subscriptions/shared/count", this_node.subscriptions_shared),
+        ("topics/count", this_node.topics),
+        ("relations/count", this_node.relations),
+        ("clients/count", this_node.clients),
+        ("sessions/count", this_node.sessions),
+    ];
+    for (suffix, value) in per_node {
+        publish_meter(&*retain_storage, &*shared, &node_meter_topic(prefix, node_id, suffix), value).await;
+    }
+    // No `subscriptions/count` here: `Meters::all_nodes` has no real cross-node aggregation for
+    // it yet, and publishing the local count under a `cluster/` topic would mislabel it.
+    let cluster = [("clients/count", all_nodes.clients), ("sessions/count", all_nodes.sessions)];
+    for (suffix, value) in cluster {
+        publish_meter(&*retain_storage, &*shared, &cluster_meter_topic(prefix, suffix), value).await;
+    }
+}
+
+///Retains `value` under `topic` and also forwards it to already-subscribed clients, so
+///existing subscribers see each interval's update live rather than only the next (re)subscriber
+///picking it up from the retained store.
+async fn publish_meter(
+    retain_storage: &dyn crate::broker::RetainStorage,
+    shared: &dyn crate::broker::Shared,
+    topic: &str,
+    value: usize,
+) {
+    let topic_name = TopicName::from(topic.to_string());
+    let payload = Bytes::from(value.to_string());
+    let publish_time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as i64).unwrap_or(0);
+
+    let retain_msg =
+        Retain { qos: QoS::AtMostOnce, payload: payload.clone(), publish_time, message_expiry_interval: None };
+    let _ = retain_storage.set(&topic_name, retain_msg).await;
+
+    let publish_msg = Publish::retain(topic_name, QoS::AtMostOnce, payload);
+    let _ = shared.forwards(From::system(), publish_msg).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_meter_topic_is_scoped_under_the_node() {
+        assert_eq!(node_meter_topic("$SYS/brokers", 1, "clients/count"), "$SYS/brokers/1/clients/count");
+    }
+
+    #[test]
+    fn cluster_meter_topic_uses_a_distinct_cluster_segment() {
+        assert_eq!(cluster_meter_topic("$SYS/brokers", "clients/count"), "$SYS/brokers/cluster/clients/count");
+    }
+}
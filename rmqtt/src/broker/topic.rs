@@ -0,0 +1,132 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+
+use crate::broker::SubRelationsMap;
+use crate::TopicName;
+
+///Matches a concrete `topic` against a subscription `topic_filter`, applying plain `+`/`#`
+///segment wildcards. Per MQTT 3.1.1/5 §4.7.2, a filter starting with a wildcard never matches
+///a topic starting with `$` (e.g. `$SYS/...`) unless the filter itself starts with `$`.
+pub fn matches(topic: &str, topic_filter: &str) -> bool {
+    if topic.starts_with('$') && !topic_filter.starts_with('$') {
+        return false;
+    }
+    let mut topic_segs = topic.split('/');
+    let mut filter_segs = topic_filter.split('/');
+    loop {
+        match (topic_segs.next(), filter_segs.next()) {
+            (_, Some("#")) => return true,
+            (Some(_), Some("+")) => continue,
+            (Some(t), Some(f)) if t == f => continue,
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+struct CacheEntry {
+    generation: u64,
+    relations: SubRelationsMap,
+}
+
+///Generation-stamped, LRU-bounded cache in front of `Router::matches`; see `DefaultRouter`.
+pub struct MatchCache {
+    generation: AtomicU64,
+    entries: Mutex<lru::LruCache<TopicName, CacheEntry>>,
+}
+
+impl MatchCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            entries: Mutex::new(lru::LruCache::new(NonZeroUsize::new(capacity.max(1)).unwrap())),
+        }
+    }
+
+    ///Generation at the time of call; pass to `insert` to stamp a not-yet-stale entry.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    ///Invalidate every cached entry.
+    #[inline]
+    pub fn invalidate_all(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    ///Drop only the cached topics for which `matches_filter` returns `true`.
+    pub fn invalidate_matching<F>(&self, matches_filter: F)
+    where
+        F: Fn(&TopicName) -> bool,
+    {
+        let mut entries = self.entries.lock();
+        let stale: Vec<TopicName> =
+            entries.iter().filter(|(topic, _)| matches_filter(topic)).map(|(topic, _)| topic.clone()).collect();
+        for topic in stale {
+            entries.pop(&topic);
+        }
+    }
+
+    ///Returns the cached relations for `topic` if present and not stale.
+    pub fn get(&self, topic: &TopicName) -> Option<SubRelationsMap> {
+        let current = self.generation();
+        let mut entries = self.entries.lock();
+        match entries.get(topic) {
+            Some(entry) if entry.generation == current => Some(entry.relations.clone()),
+            Some(_) => {
+                entries.pop(topic);
+                None
+            }
+            None => None,
+        }
+    }
+
+    ///Caches `relations` as the result for `topic`, stamped with `generation`.
+    pub fn insert(&self, topic: TopicName, generation: u64, relations: SubRelationsMap) {
+        self.entries.lock().put(topic, CacheEntry { generation, relations });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_segments_must_match() {
+        assert!(matches("a/b", "a/b"));
+        assert!(!matches("a/b", "a/c"));
+    }
+
+    #[test]
+    fn plus_matches_one_segment() {
+        assert!(matches("a/b", "a/+"));
+        assert!(!matches("a/b/c", "a/+"));
+    }
+
+    #[test]
+    fn hash_matches_remaining_segments() {
+        assert!(matches("a/b/c", "a/#"));
+        assert!(matches("a", "a/#"));
+    }
+
+    #[test]
+    fn leading_wildcard_excludes_dollar_topics() {
+        assert!(!matches("$SYS/brokers/count", "#"));
+        assert!(!matches("$SYS/brokers/count", "+/brokers/count"));
+        assert!(matches("$SYS/brokers/count", "$SYS/#"));
+    }
+
+    #[test]
+    fn cache_entry_goes_stale_after_invalidate() {
+        let cache = MatchCache::new(8);
+        let topic = TopicName::from("a/b".to_string());
+        let generation = cache.generation();
+        cache.insert(topic.clone(), generation, SubRelationsMap::default());
+        assert!(cache.get(&topic).is_some());
+        cache.invalidate_all();
+        assert!(cache.get(&topic).is_none());
+    }
+}
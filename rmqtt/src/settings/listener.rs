@@ -0,0 +1,29 @@
+///Per-listener configuration consumed by the broker (shared subscriptions, retain, and the
+///subscription/queue admission control in `crate::broker::queue` and `Router::quota_exceeded`).
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub shared_subscription: bool,
+    pub retain_available: bool,
+
+    ///Broker-wide cap on live subscription relations; `0` disables the cap.
+    pub max_active_subscriptions: usize,
+    ///Max buffered publishes per session outbound queue; `0` is unbounded.
+    pub queue_capacity: usize,
+    ///Max cumulative payload bytes buffered per session outbound queue; `0` is unbounded.
+    pub queue_capacity_bytes: usize,
+    ///Reject new messages instead of dropping the oldest buffered one once a queue is full.
+    pub queue_reject_new: bool,
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Self {
+            shared_subscription: true,
+            retain_available: true,
+            max_active_subscriptions: 0,
+            queue_capacity: 1024,
+            queue_capacity_bytes: 16 * 1024 * 1024,
+            queue_reject_new: false,
+        }
+    }
+}